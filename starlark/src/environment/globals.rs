@@ -0,0 +1,39 @@
+/*
+ * Copyright 2018 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Builds the [`Globals`] available to a Starlark program by default.
+
+use crate::environment::Globals;
+use crate::environment::GlobalsBuilder;
+use crate::stdlib::standard_environment;
+
+impl GlobalsBuilder {
+    /// A builder already populated with everything the standard environment
+    /// ships: `eval_type`/`isinstance`, `math`, `fraction`, and so on.
+    pub(crate) fn standard() -> Self {
+        let mut builder = GlobalsBuilder::new();
+        standard_environment(&mut builder);
+        builder
+    }
+}
+
+impl Globals {
+    /// The standard set of globals available to every Starlark program.
+    pub fn standard() -> Self {
+        GlobalsBuilder::standard().build()
+    }
+}
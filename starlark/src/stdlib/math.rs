@@ -0,0 +1,225 @@
+/*
+ * Copyright 2018 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The `math` global functions.
+
+use num_bigint::BigInt;
+use num_traits::FromPrimitive;
+use starlark_derive::starlark_module;
+
+use crate as starlark;
+use crate::environment::GlobalsBuilder;
+use crate::values::num::Num;
+use crate::values::Heap;
+use crate::values::Value;
+
+/// The actual float operations, swapped out depending on whether `std` is available:
+/// with `std` we use the inherent `f64` methods, without it we route through `libm`
+/// so this module keeps working in `no_std` embeddings.
+#[cfg(feature = "std")]
+mod imp {
+    pub(super) fn floor(x: f64) -> f64 {
+        x.floor()
+    }
+    pub(super) fn ceil(x: f64) -> f64 {
+        x.ceil()
+    }
+    pub(super) fn round(x: f64) -> f64 {
+        x.round()
+    }
+    pub(super) fn trunc(x: f64) -> f64 {
+        x.trunc()
+    }
+    pub(super) fn sqrt(x: f64) -> f64 {
+        x.sqrt()
+    }
+    pub(super) fn pow(x: f64, y: f64) -> f64 {
+        x.powf(y)
+    }
+    pub(super) fn log(x: f64, base: f64) -> f64 {
+        x.log(base)
+    }
+    pub(super) fn exp(x: f64) -> f64 {
+        x.exp()
+    }
+    pub(super) fn sin(x: f64) -> f64 {
+        x.sin()
+    }
+    pub(super) fn cos(x: f64) -> f64 {
+        x.cos()
+    }
+    pub(super) fn tan(x: f64) -> f64 {
+        x.tan()
+    }
+}
+
+#[cfg(not(feature = "std"))]
+mod imp {
+    pub(super) fn floor(x: f64) -> f64 {
+        libm::floor(x)
+    }
+    pub(super) fn ceil(x: f64) -> f64 {
+        libm::ceil(x)
+    }
+    pub(super) fn round(x: f64) -> f64 {
+        libm::round(x)
+    }
+    pub(super) fn trunc(x: f64) -> f64 {
+        libm::trunc(x)
+    }
+    pub(super) fn sqrt(x: f64) -> f64 {
+        libm::sqrt(x)
+    }
+    pub(super) fn pow(x: f64, y: f64) -> f64 {
+        libm::pow(x, y)
+    }
+    pub(super) fn log(x: f64, base: f64) -> f64 {
+        libm::log(x) / libm::log(base)
+    }
+    pub(super) fn exp(x: f64) -> f64 {
+        libm::exp(x)
+    }
+    pub(super) fn sin(x: f64) -> f64 {
+        libm::sin(x)
+    }
+    pub(super) fn cos(x: f64) -> f64 {
+        libm::cos(x)
+    }
+    pub(super) fn tan(x: f64) -> f64 {
+        libm::tan(x)
+    }
+}
+
+/// Allocate `f` as an int when it is exactly representable as one (matching
+/// Python/Starlark-go, where `floor`/`ceil` return an int), otherwise as a float.
+///
+/// Uses `as_i64` rather than `as_int` so that integral results beyond
+/// `i32::MAX` (e.g. `math.floor(1e15)`) still become an int instead of
+/// silently staying a float.
+fn int_or_float<'v>(heap: &'v Heap, f: f64) -> Value<'v> {
+    match Num::from(f).as_i64() {
+        Some(i) => heap.alloc(i),
+        // Integral but beyond `i64`: still an int, as a big int.
+        None if Num::from(f).is_exact_int() => match BigInt::from_f64(f) {
+            Some(b) => heap.alloc(b),
+            None => heap.alloc(f),
+        },
+        None => heap.alloc(f),
+    }
+}
+
+#[starlark_module]
+pub(crate) fn register_math(globals: &mut GlobalsBuilder) {
+    /// Returns the largest integer that is <= x, as an int if it fits one exactly.
+    fn floor<'v>(
+        #[starlark(require = pos)] x: Num<'v>,
+        heap: &'v Heap,
+    ) -> anyhow::Result<Value<'v>> {
+        Ok(int_or_float(heap, imp::floor(x.as_float())))
+    }
+
+    /// Returns the smallest integer that is >= x, as an int if it fits one exactly.
+    fn ceil<'v>(
+        #[starlark(require = pos)] x: Num<'v>,
+        heap: &'v Heap,
+    ) -> anyhow::Result<Value<'v>> {
+        Ok(int_or_float(heap, imp::ceil(x.as_float())))
+    }
+
+    /// Returns x rounded to the nearest integer, as an int if it fits one exactly.
+    fn round<'v>(
+        #[starlark(require = pos)] x: Num<'v>,
+        heap: &'v Heap,
+    ) -> anyhow::Result<Value<'v>> {
+        Ok(int_or_float(heap, imp::round(x.as_float())))
+    }
+
+    /// Returns x with its fractional part removed, as an int if it fits one exactly.
+    fn trunc<'v>(
+        #[starlark(require = pos)] x: Num<'v>,
+        heap: &'v Heap,
+    ) -> anyhow::Result<Value<'v>> {
+        Ok(int_or_float(heap, imp::trunc(x.as_float())))
+    }
+
+    /// Returns the square root of x.
+    fn sqrt(#[starlark(require = pos)] x: Num) -> anyhow::Result<f64> {
+        Ok(imp::sqrt(x.as_float()))
+    }
+
+    /// Returns x raised to the power y.
+    fn pow(
+        #[starlark(require = pos)] x: Num,
+        #[starlark(require = pos)] y: Num,
+    ) -> anyhow::Result<f64> {
+        Ok(imp::pow(x.as_float(), y.as_float()))
+    }
+
+    /// Returns the natural logarithm of x, or the logarithm of x in the given base.
+    fn log(
+        #[starlark(require = pos)] x: Num,
+        #[starlark(require = pos, default = std::f64::consts::E)] base: f64,
+    ) -> anyhow::Result<f64> {
+        Ok(imp::log(x.as_float(), base))
+    }
+
+    /// Returns e raised to the power x.
+    fn exp(#[starlark(require = pos)] x: Num) -> anyhow::Result<f64> {
+        Ok(imp::exp(x.as_float()))
+    }
+
+    /// Returns the sine of x, in radians.
+    fn sin(#[starlark(require = pos)] x: Num) -> anyhow::Result<f64> {
+        Ok(imp::sin(x.as_float()))
+    }
+
+    /// Returns the cosine of x, in radians.
+    fn cos(#[starlark(require = pos)] x: Num) -> anyhow::Result<f64> {
+        Ok(imp::cos(x.as_float()))
+    }
+
+    /// Returns the tangent of x, in radians.
+    fn tan(#[starlark(require = pos)] x: Num) -> anyhow::Result<f64> {
+        Ok(imp::tan(x.as_float()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert;
+
+    #[test]
+    fn test_math_floor_ceil_return_int() {
+        assert::eq("3", "math.floor(3.7)");
+        assert::eq("4", "math.ceil(3.2)");
+        assert::eq("3", "math.trunc(3.9)");
+        assert::eq("4", "math.round(3.6)");
+    }
+
+    #[test]
+    fn test_math_sqrt_pow() {
+        assert::eq("2.0", "math.sqrt(4)");
+        assert::eq("8.0", "math.pow(2, 3)");
+    }
+
+    #[test]
+    fn test_math_floor_returns_int_beyond_i32() {
+        // `1e15` is well beyond `i32::MAX` but is exactly representable as an
+        // `i64`, so it should still come back as an int, not a float.
+        assert::eq("1000000000000000", "math.floor(1e15)");
+    }
+}
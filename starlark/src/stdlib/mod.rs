@@ -0,0 +1,38 @@
+/*
+ * Copyright 2018 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Global functions provided by the standard environment.
+
+pub(crate) mod math;
+pub(crate) mod rational;
+
+use crate::environment::GlobalsBuilder;
+use crate::stdlib::math::register_math;
+use crate::stdlib::rational::register_rational;
+use crate::values::typing::type_compiled::globals::register_eval_type;
+
+/// Register every global that ships with the standard environment:
+/// `eval_type`/`isinstance`, the `math` module, `fraction`, and so on.
+///
+/// Called from [`GlobalsBuilder::standard`](crate::environment::GlobalsBuilder::standard),
+/// which backs [`Globals::standard`](crate::environment::Globals::standard) -
+/// this is the only place that should call the individual `register_*` functions below.
+pub(crate) fn standard_environment(globals: &mut GlobalsBuilder) {
+    register_eval_type(globals);
+    globals.namespace("math", register_math);
+    register_rational(globals);
+}
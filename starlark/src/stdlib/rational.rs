@@ -0,0 +1,71 @@
+/*
+ * Copyright 2018 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The `fraction` global, the only way Starlark code can construct a
+//! `rational` value (there is no literal syntax for one).
+
+use num_bigint::BigInt;
+use starlark_derive::starlark_module;
+
+use crate as starlark;
+use crate::environment::GlobalsBuilder;
+use crate::values::types::rational::StarlarkRational;
+use crate::values::Heap;
+use crate::values::Value;
+
+#[starlark_module]
+pub(crate) fn register_rational(globals: &mut GlobalsBuilder) {
+    /// Construct the exact fraction `numerator/denominator`, e.g.
+    /// `fraction(1, 3) + fraction(1, 3) + fraction(1, 3) == 1`.
+    fn fraction<'v>(
+        #[starlark(require = pos)] numerator: i32,
+        #[starlark(require = pos)] denominator: i32,
+        heap: &'v Heap,
+    ) -> anyhow::Result<Value<'v>> {
+        if denominator == 0 {
+            return Err(anyhow::anyhow!("fraction() denominator must not be zero"));
+        }
+        Ok(heap.alloc(StarlarkRational::new(
+            BigInt::from(numerator),
+            BigInt::from(denominator),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert;
+
+    #[test]
+    fn test_fraction_exact_sum() {
+        assert::eq(
+            "True",
+            "(fraction(1, 3) + fraction(1, 3) + fraction(1, 3)) == 1",
+        );
+    }
+
+    #[test]
+    fn test_fraction_widens_to_float_with_float() {
+        assert::eq("True", "(fraction(1, 2) + 0.5) == 1.0");
+    }
+
+    #[test]
+    fn test_fraction_addition_is_commutative() {
+        assert::eq("True", "(1 + fraction(1, 3)) == (fraction(1, 3) + 1)");
+        assert::eq("True", "(0.5 + fraction(1, 2)) == 1.0");
+    }
+}
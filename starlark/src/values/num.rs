@@ -21,11 +21,16 @@ use std::cmp::Ordering;
 
 use dupe::Dupe;
 use either::Either;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::FromPrimitive;
+use num_traits::ToPrimitive;
 
 use crate::collections::StarlarkHashValue;
 use crate::values::type_repr::StarlarkTypeRepr;
 use crate::values::types::float::StarlarkFloat;
 use crate::values::types::int_or_big::StarlarkIntRef;
+use crate::values::types::rational::StarlarkRational;
 use crate::values::UnpackValue;
 use crate::values::Value;
 use crate::values::ValueLike;
@@ -39,11 +44,12 @@ use crate::values::ValueLike;
 pub(crate) enum Num<'v> {
     Int(StarlarkIntRef<'v>),
     Float(f64),
+    Rational(&'v StarlarkRational),
 }
 
 impl<'v> StarlarkTypeRepr for Num<'v> {
     fn starlark_type_repr() -> String {
-        Either::<StarlarkIntRef, StarlarkFloat>::starlark_type_repr()
+        Either::<Either<StarlarkIntRef, StarlarkFloat>, StarlarkRational>::starlark_type_repr()
     }
 }
 
@@ -58,6 +64,8 @@ impl<'v> UnpackValue<'v> for Num<'v> {
             Some(Num::Int(i))
         } else if let Some(f) = value.downcast_ref::<StarlarkFloat>() {
             Some(Num::Float(f.0))
+        } else if let Some(r) = value.downcast_ref::<StarlarkRational>() {
+            Some(Num::Rational(r))
         } else {
             None
         }
@@ -70,6 +78,7 @@ impl<'v> Num<'v> {
         match self {
             Self::Int(i) => i.to_f64(),
             Self::Float(f) => *f,
+            Self::Rational(r) => r.to_f64(),
         }
     }
 
@@ -83,39 +92,105 @@ impl<'v> Num<'v> {
         match self {
             Self::Int(i) => i.to_i32(),
             Self::Float(f) => Self::f64_to_i32_exact(*f),
+            Self::Rational(r) => r.to_i32(),
         }
     }
 
-    /// Get hash of the underlying number
-    pub(crate) fn get_hash_64(self) -> u64 {
-        fn float_hash(f: f64) -> u64 {
-            if f.is_nan() {
-                // all possible NaNs should hash to the same value
-                0
-            } else if f.is_infinite() {
-                u64::MAX
-            } else if f == 0.0 {
-                // Both 0.0 and -0.0 need the same hash, but are both equal to 0.0
-                0.0f64.to_bits()
-            } else {
-                f.to_bits()
+    fn f64_to_i64_exact(f: f64) -> Option<i64> {
+        // `f as i64` saturates on out-of-range floats instead of wrapping, so e.g.
+        // `2^63` would cast to `i64::MAX` and then round-trip back to `2^63` as a
+        // float, falsely looking exact. Reject out-of-range values up front.
+        if f.is_finite() && f >= -(2f64.powi(63)) && f < 2f64.powi(63) {
+            let i = f as i64;
+            if i as f64 == f { Some(i) } else { None }
+        } else {
+            None
+        }
+    }
+
+    fn f64_to_u64_exact(f: f64) -> Option<u64> {
+        if f.is_finite() && f >= 0.0 && f < 2f64.powi(64) {
+            let i = f as u64;
+            if i as f64 == f { Some(i) } else { None }
+        } else {
+            None
+        }
+    }
+
+    /// Get underlying value as `i64` (if it can be precisely expressed as one)
+    pub(crate) fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Int(StarlarkIntRef::Small(i)) => Some(*i as i64),
+            Self::Int(StarlarkIntRef::Big(b)) => b.get().to_i64(),
+            Self::Float(f) => Self::f64_to_i64_exact(*f),
+            Self::Rational(r) if r.get().is_integer() => r.get().to_integer().to_i64(),
+            Self::Rational(_) => None,
+        }
+    }
+
+    /// Get underlying value as `u64` (if it can be precisely expressed as one)
+    pub(crate) fn as_u64(&self) -> Option<u64> {
+        match self {
+            Self::Int(StarlarkIntRef::Small(i)) => u64::try_from(*i).ok(),
+            Self::Int(StarlarkIntRef::Big(b)) => b.get().to_u64(),
+            Self::Float(f) => Self::f64_to_u64_exact(*f),
+            Self::Rational(r) if r.get().is_integer() => r.get().to_integer().to_u64(),
+            Self::Rational(_) => None,
+        }
+    }
+
+    /// Whether this value is an int, or a float/rational that represents one exactly.
+    pub(crate) fn is_exact_int(&self) -> bool {
+        match self {
+            Self::Int(_) => true,
+            Self::Float(f) => f.is_finite() && f.fract() == 0.0,
+            Self::Rational(r) => r.get().is_integer(),
+        }
+    }
+
+    /// The exact value of a finite number, as a rational. `None` for NaN/infinity,
+    /// which have no exact value to compare or hash by.
+    ///
+    /// This is the single source of truth for cross-type numeric equality: two
+    /// `Num`s are equal iff their exact values agree, regardless of which variant
+    /// (`Int`, `Float`, `Rational`) holds them, and `get_hash_64` hashes by the
+    /// same exact value so equal values always hash the same.
+    fn as_exact_rational(&self) -> Option<BigRational> {
+        match self {
+            Self::Int(StarlarkIntRef::Small(i)) => {
+                Some(BigRational::from_integer(BigInt::from(*i)))
             }
+            Self::Int(StarlarkIntRef::Big(b)) => Some(BigRational::from_integer(b.get().clone())),
+            Self::Rational(r) => Some(r.get().clone()),
+            Self::Float(f) => BigRational::from_f64(*f),
         }
+    }
 
-        match (self.as_int(), self) {
+    /// Get hash of the underlying number
+    pub(crate) fn get_hash_64(self) -> u64 {
+        match self.as_int() {
             // equal ints and floats should have the same hash
-            (Some(i), _) => i as u64,
-            (None, Self::Float(f)) => float_hash(f),
-            (None, Self::Int(StarlarkIntRef::Small(i))) => {
-                // shouldn't happen - as_int() should have resulted in an int
-                i as u64
-            }
-            (None, Self::Int(StarlarkIntRef::Big(b))) => {
-                // Not perfect, but OK: `1000000000000000000000003` and `1000000000000000000000005`
-                // flush to the same float, and neither is exact float,
-                // so we could use better hash for such numbers.
-                float_hash(b.to_f64())
-            }
+            Some(i) => i as u64,
+            None => match self {
+                // A plain float (the common case for hashing a non-integral
+                // number) never needs to go through `BigRational`: an `Int::Big`
+                // or `Rational` equal to it is exactly this float, and the
+                // exactness checks below send those to `float_hash` as well.
+                Self::Float(f) => float_hash(f),
+                Self::Int(StarlarkIntRef::Big(b)) => match b.get().to_f64() {
+                    Some(f) if BigInt::from_f64(f).as_ref() == Some(b.get()) => float_hash(f),
+                    _ => big_int_hash(b.get()),
+                },
+                Self::Rational(r) => match r.get().to_f64() {
+                    Some(f) if BigRational::from_f64(f).as_ref() == Some(r.get()) => {
+                        float_hash(f)
+                    }
+                    // An integral rational must hash like the equal `Int::Big`.
+                    _ if r.get().is_integer() => big_int_hash(&r.get().to_integer()),
+                    _ => rational_hash(r.get()),
+                },
+                Self::Int(StarlarkIntRef::Small(_)) => unreachable!("handled by `as_int` above"),
+            },
         }
     }
 
@@ -124,6 +199,50 @@ impl<'v> Num<'v> {
     }
 }
 
+/// Hash a float so that all NaNs collide, +-infinity hashes distinctly from
+/// any finite value, and `0.0`/`-0.0` (which compare equal) hash the same.
+fn float_hash(f: f64) -> u64 {
+    if f.is_nan() {
+        // all possible NaNs should hash to the same value
+        0
+    } else if f.is_infinite() {
+        u64::MAX
+    } else if f == 0.0 {
+        // Both 0.0 and -0.0 need the same hash, but are both equal to 0.0
+        0.0f64.to_bits()
+    } else {
+        f.to_bits()
+    }
+}
+
+/// FNV-1a over a big int's bytes, for the (rare) case where it isn't exactly
+/// representable as an `f64` and so can't share `float_hash`'s cheap path.
+fn big_int_hash(b: &BigInt) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for byte in b.to_signed_bytes_le() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Hash a non-integral (reduced) rational that isn't exactly an `f64` by the
+/// bytes of its numerator and denominator; only an equal rational can equal
+/// it, and that one reduces to the same bytes.
+fn rational_hash(r: &BigRational) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for byte in r
+        .numer()
+        .to_signed_bytes_le()
+        .into_iter()
+        .chain(r.denom().to_signed_bytes_le())
+    {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
 impl<'v> From<i32> for Num<'v> {
     fn from(i: i32) -> Self {
         Self::Int(StarlarkIntRef::Small(i))
@@ -136,12 +255,38 @@ impl<'v> From<f64> for Num<'v> {
     }
 }
 
+/// Whether exactly representing this value may require more than `f64` can
+/// hold, i.e. it isn't a small int or a plain float. `i32 -> f64` is always
+/// lossless, so `Int::Small`/`Float` operands never need the `BigRational`
+/// path below - only `Int::Big` and `Rational` do.
+fn needs_exact_rational(n: &Num) -> bool {
+    matches!(n, Num::Int(StarlarkIntRef::Big(_)) | Num::Rational(_))
+}
+
 /// This is total eq per starlark spec, not Rust's partial eq.
 impl<'v> PartialEq for Num<'v> {
     fn eq(&self, other: &Self) -> bool {
         if let (Num::Int(a), Num::Int(b)) = (self, other) {
             a == b
+        } else if let (Num::Rational(a), Num::Rational(b)) = (self, other) {
+            a.get() == b.get()
+        } else if needs_exact_rational(self) || needs_exact_rational(other) {
+            // Compare exact values rather than lossy `as_float()` round-trips: a
+            // `Big`/`Rational` that doesn't fit a float exactly must not compare
+            // equal to the nearest float, since `get_hash_64` (built on the same
+            // exact value) would then disagree and break the Eq/Hash contract.
+            match (self.as_exact_rational(), other.as_exact_rational()) {
+                (Some(a), Some(b)) => a == b,
+                _ => {
+                    StarlarkFloat::compare_impl(self.as_float(), other.as_float())
+                        == Ordering::Equal
+                }
+            }
         } else {
+            // Remaining case: `Int::Small` vs `Float` (either order) or `Float`
+            // vs `Float`. Neither side can lose precision converting to `f64`,
+            // so the cheap comparison is already exact - no `BigRational`
+            // allocation needed on this hot path.
             StarlarkFloat::compare_impl(self.as_float(), other.as_float()) == Ordering::Equal
         }
     }
@@ -159,6 +304,13 @@ impl<'v> Ord for Num<'v> {
     fn cmp(&self, other: &Self) -> Ordering {
         if let (Num::Int(a), Num::Int(b)) = (self, other) {
             a.cmp(b)
+        } else if let (Num::Rational(a), Num::Rational(b)) = (self, other) {
+            a.get().cmp(b.get())
+        } else if needs_exact_rational(self) || needs_exact_rational(other) {
+            match (self.as_exact_rational(), other.as_exact_rational()) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                _ => StarlarkFloat::compare_impl(self.as_float(), other.as_float()),
+            }
         } else {
             StarlarkFloat::compare_impl(self.as_float(), other.as_float())
         }
@@ -226,6 +378,46 @@ mod tests {
         assert_eq!(Num::Float(f64::NEG_INFINITY).as_int(), None);
     }
 
+    #[test]
+    fn test_conversion_to_i64() {
+        assert_eq!(Num::Int(StarlarkIntRef::Small(42)).as_i64(), Some(42));
+        assert_eq!(Num::Int(StarlarkIntRef::Small(-42)).as_i64(), Some(-42));
+
+        assert_eq!(Num::Float(42_f64).as_i64(), Some(42));
+        assert_eq!(Num::Float(42.75).as_i64(), None);
+        assert_eq!(Num::Float(f64::NAN).as_i64(), None);
+        assert_eq!(Num::Float(f64::INFINITY).as_i64(), None);
+
+        // `2^63` is out of `i64` range but `2^63 as i64` saturates to
+        // `i64::MAX`, which then round-trips back to `2^63` as a float and
+        // would look exact if the cast weren't range-checked first.
+        assert_eq!(Num::Float(2f64.powi(63)).as_i64(), None);
+        // The next float below `i64::MIN` (`-(2^63) - 1.0` would round back to `i64::MIN`).
+        assert_eq!(Num::Float(-(2f64.powi(63)) - 2048.0).as_i64(), None);
+    }
+
+    #[test]
+    fn test_conversion_to_u64() {
+        assert_eq!(Num::Int(StarlarkIntRef::Small(42)).as_u64(), Some(42));
+        assert_eq!(Num::Int(StarlarkIntRef::Small(-42)).as_u64(), None);
+
+        assert_eq!(Num::Float(42_f64).as_u64(), Some(42));
+        assert_eq!(Num::Float(-42_f64).as_u64(), None);
+        assert_eq!(Num::Float(42.75).as_u64(), None);
+
+        // Same saturating-cast trap as `as_i64`, at the `u64` boundary.
+        assert_eq!(Num::Float(2f64.powi(64)).as_u64(), None);
+    }
+
+    #[test]
+    fn test_is_exact_int() {
+        assert!(Num::Int(StarlarkIntRef::Small(42)).is_exact_int());
+        assert!(Num::Float(42_f64).is_exact_int());
+        assert!(!Num::Float(42.5).is_exact_int());
+        assert!(!Num::Float(f64::NAN).is_exact_int());
+        assert!(!Num::Float(f64::INFINITY).is_exact_int());
+    }
+
     #[test]
     fn test_hashing() {
         assert_eq!(
@@ -253,4 +445,47 @@ mod tests {
         assert_eq!(Num::Float(f64::INFINITY), Num::Float(f64::INFINITY));
         assert_eq!(Num::Int(StarlarkIntRef::Small(10)), Num::Float(10.0));
     }
+
+    #[test]
+    fn test_rational() {
+        let one_third = StarlarkRational::new(BigInt::from(1), BigInt::from(3));
+        let sum = StarlarkRational::new(BigInt::from(1), BigInt::from(1));
+
+        assert_eq!(Num::Rational(&one_third).as_int(), None);
+        assert!(!Num::Rational(&one_third).is_exact_int());
+
+        assert_eq!(Num::Rational(&sum).as_int(), Some(1));
+        assert!(Num::Rational(&sum).is_exact_int());
+        assert_eq!(
+            Num::Rational(&sum).get_hash_64(),
+            Num::Int(StarlarkIntRef::Small(1)).get_hash_64()
+        );
+        assert_eq!(Num::Rational(&sum), Num::Int(StarlarkIntRef::Small(1)));
+    }
+
+    #[test]
+    fn test_eq_and_hash_agree_for_values_not_exactly_a_float() {
+        // 2^53 + 1 doesn't fit `f64` exactly: it rounds down to 2^53
+        // (9007199254740992.0). A naive `as_float()` comparison would wrongly
+        // call these equal; they must not be, and whenever two `Num`s *are*
+        // equal their hashes must agree too (the Eq/Hash contract).
+        let not_exactly_a_float: BigInt = "9007199254740993".parse().unwrap();
+        let big = StarlarkRational::new(not_exactly_a_float, BigInt::from(1));
+        let rounded_float = Num::Float(9007199254740992.0);
+
+        assert_ne!(Num::Rational(&big), rounded_float);
+
+        for (a, b) in [
+            (Num::Rational(&big), rounded_float),
+            (Num::Int(StarlarkIntRef::Small(1)), Num::Rational(&sum_one())),
+        ] {
+            if a == b {
+                assert_eq!(a.get_hash_64(), b.get_hash_64());
+            }
+        }
+    }
+
+    fn sum_one() -> StarlarkRational {
+        StarlarkRational::new(BigInt::from(1), BigInt::from(1))
+    }
 }
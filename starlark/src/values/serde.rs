@@ -0,0 +1,254 @@
+/*
+ * Copyright 2018 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! `serde` bridge for Starlark values: serialize/deserialize numbers without
+//! losing the int/float distinction that a lossy `f64` round-trip through
+//! JSON/YAML would otherwise erase. Rationals are serialized as their nearest
+//! `f64` and come back as floats.
+
+use std::fmt;
+
+use num_bigint::BigInt;
+use serde::de;
+use serde::de::Visitor;
+use serde::ser::Error as _;
+use serde::Serialize;
+use serde::Serializer;
+
+use crate::eval::Evaluator;
+use crate::values::num::Num;
+use crate::values::typing::ty::AbstractType;
+use crate::values::typing::type_compiled::compiled::TypeCompiled;
+use crate::values::types::int_or_big::StarlarkIntRef;
+use crate::values::Heap;
+use crate::values::Value;
+use crate::values::ValueOfUnchecked;
+
+/// Wraps a [`Value`] so it can be handed to a `serde::Serializer`, emitting
+/// ints and floats as their native tokens instead of flushing everything
+/// through `f64`.
+pub(crate) struct SerializeValue<'v>(pub(crate) Value<'v>);
+
+impl<'v> Serialize for SerializeValue<'v> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match Num::unpack_value(self.0) {
+            Some(Num::Int(StarlarkIntRef::Small(i))) => serializer.serialize_i64(i as i64),
+            Some(Num::Int(StarlarkIntRef::Big(b))) => match b.get().to_i128() {
+                Some(i) => serializer.serialize_i128(i),
+                // Outside `i128`: fall back to an arbitrary-precision decimal string,
+                // same as serde-yaml does for numbers it can't represent natively.
+                None => serializer.serialize_str(&b.get().to_string()),
+            },
+            Some(Num::Rational(r)) => serializer.serialize_f64(r.to_f64()),
+            Some(Num::Float(f)) => serializer.serialize_f64(f),
+            None => Err(S::Error::custom(format!(
+                "Cannot serialize a value of type `{}`",
+                self.0.get_type()
+            ))),
+        }
+    }
+}
+
+/// Reconstructs the narrowest exact Starlark number for a deserialized token:
+/// an `i32`-sized int, a big int, or a float for anything non-integral.
+struct NumVisitor<'v> {
+    heap: &'v Heap,
+}
+
+impl<'de, 'v> Visitor<'de> for NumVisitor<'v> {
+    type Value = Value<'v>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a Starlark int or float")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(self.heap.alloc(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(self.heap.alloc(v))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(self.heap.alloc(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(self.heap.alloc(v))
+    }
+
+    // The counterpart of `SerializeValue`'s decimal-string fallback for ints
+    // that don't fit `i128`: without this, such a token round-trips as an
+    // error instead of the big int it started as.
+    //
+    // Not every string token seen here is meant to be that fallback, though -
+    // `deserialize_typed` reuses this visitor for values of any expected type,
+    // including `str` itself. So a token that isn't a valid decimal integer is
+    // kept as a genuine Starlark string rather than rejected outright, and it's
+    // left to `TypeCompiled::matches` to produce the right `Expected type ...`
+    // diagnostic if the caller actually wanted a number.
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match v.parse::<BigInt>() {
+            Ok(big) => Ok(self.heap.alloc(big)),
+            Err(_) => Ok(self.heap.alloc(v)),
+        }
+    }
+}
+
+/// Deserializes a single Starlark number, allocated on `heap`. A string token
+/// that isn't a decimal integer comes back as a Starlark string.
+pub(crate) fn deserialize_number<'v, 'de, D>(
+    heap: &'v Heap,
+    deserializer: D,
+) -> Result<Value<'v>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    deserializer.deserialize_any(NumVisitor { heap })
+}
+
+/// Deserializes a value and checks it against `ty`, reusing `eval_type`/`isinstance`'s
+/// machinery so a mismatch produces the same "Expected type ... but got ..." diagnostic.
+pub(crate) fn deserialize_typed<'v, 'de, D>(
+    ty: ValueOfUnchecked<'v, AbstractType>,
+    eval: &mut Evaluator<'v, '_, '_>,
+    deserializer: D,
+) -> anyhow::Result<Value<'v>>
+where
+    D: serde::Deserializer<'de>,
+    D::Error: Into<anyhow::Error>,
+{
+    let value = deserialize_number(eval.heap(), deserializer).map_err(Into::into)?;
+    let compiled = TypeCompiled::new_with_deprecation(ty.get(), eval)?;
+    if compiled.matches(value) {
+        Ok(value)
+    } else {
+        // Same diagnostic `isinstance`/`eval_type` produce on a type mismatch.
+        Err(anyhow::anyhow!(
+            "Expected type `{}` but got `{}`",
+            compiled,
+            value.get_type()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigInt;
+    use starlark_derive::starlark_module;
+
+    use super::*;
+    use crate as starlark;
+    use crate::assert::Assert;
+    use crate::environment::GlobalsBuilder;
+    use crate::values::serde::SerializeValue;
+    use crate::values::Heap;
+
+    #[test]
+    fn test_serialize_small_int_as_integer_token() {
+        let heap = Heap::new();
+        let value = heap.alloc(42);
+        assert_eq!(serde_json::to_string(&SerializeValue(value)).unwrap(), "42");
+    }
+
+    #[test]
+    fn test_serialize_float_as_float_token() {
+        let heap = Heap::new();
+        let value = heap.alloc(1.5);
+        assert_eq!(
+            serde_json::to_string(&SerializeValue(value)).unwrap(),
+            "1.5"
+        );
+    }
+
+    #[test]
+    fn test_deserialize_small_int_round_trips() {
+        let heap = Heap::new();
+        let mut de = serde_json::Deserializer::from_str("42");
+        let value = deserialize_number(&heap, &mut de).unwrap();
+        assert_eq!(Num::unpack_value(value).unwrap().as_int(), Some(42));
+    }
+
+    #[test]
+    fn test_deserialize_big_int_beyond_i128_round_trips() {
+        let heap = Heap::new();
+        let big: BigInt = "1000000000000000000000000000000000000000"
+            .parse()
+            .unwrap();
+        let value = heap.alloc(big.clone());
+
+        // Serializes as a decimal string (too large for `i128`) ...
+        let json = serde_json::to_string(&SerializeValue(value)).unwrap();
+        assert_eq!(json, format!("\"{big}\""));
+
+        // ... and must parse back to the same big int, not an error.
+        let mut de = serde_json::Deserializer::from_str(&json);
+        let round_tripped = deserialize_number(&heap, &mut de).unwrap();
+        assert_eq!(
+            Num::unpack_value(round_tripped).unwrap(),
+            Num::unpack_value(value).unwrap()
+        );
+    }
+
+    #[starlark_module]
+    fn register_test_globals(globals: &mut GlobalsBuilder) {
+        fn deserialize_json_as<'v>(
+            #[starlark(require = pos)] json: &str,
+            #[starlark(require = pos)] ty: ValueOfUnchecked<'v, AbstractType>,
+            eval: &mut Evaluator<'v, '_, '_>,
+        ) -> anyhow::Result<Value<'v>> {
+            let mut de = serde_json::Deserializer::from_str(json);
+            deserialize_typed(ty, eval, &mut de)
+        }
+    }
+
+    #[test]
+    fn test_deserialize_typed_mismatch_error() {
+        let mut a = Assert::new();
+        a.globals_add(register_test_globals);
+        a.fail(
+            r#"deserialize_json_as('"hi"', int)"#,
+            "Expected type `int` but got `str`",
+        );
+    }
+
+    #[test]
+    fn test_deserialize_typed_matching_type() {
+        let mut a = Assert::new();
+        a.globals_add(register_test_globals);
+        a.eq("42", "deserialize_json_as('42', int)");
+    }
+}
@@ -0,0 +1,127 @@
+/*
+ * Copyright 2018 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The rational number type: an exact fraction of two arbitrary-precision integers.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::Display;
+
+use allocative::Allocative;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::ToPrimitive;
+
+use crate as starlark;
+use crate::any::ProvidesStaticType;
+use crate::collections::StarlarkHashValue;
+use crate::values::num::Num;
+use crate::values::starlark_value;
+use crate::values::types::int_or_big::StarlarkIntRef;
+use crate::values::Heap;
+use crate::values::StarlarkValue;
+use crate::values::UnpackValue;
+use crate::values::Value;
+use crate::values::ValueLike;
+
+/// An exact fraction, backing the `Num::Rational` variant so arithmetic like
+/// `1/3 + 1/3 + 1/3` stays exact instead of drifting through `f64`.
+#[derive(Clone, Debug, ProvidesStaticType, Allocative)]
+pub struct StarlarkRational(pub(crate) BigRational);
+
+impl Display for StarlarkRational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.0.numer(), self.0.denom())
+    }
+}
+
+impl StarlarkRational {
+    pub(crate) fn new(numer: BigInt, denom: BigInt) -> Self {
+        Self(BigRational::new(numer, denom))
+    }
+
+    pub(crate) fn get(&self) -> &BigRational {
+        &self.0
+    }
+
+    /// Nearest `f64` to this fraction (Starlark's usual promotion for mixed
+    /// rational/float arithmetic and comparisons).
+    pub(crate) fn to_f64(&self) -> f64 {
+        self.0.to_f64().unwrap_or(f64::NAN)
+    }
+
+    /// The value as an `i32`, if this fraction is a whole number that fits.
+    pub(crate) fn to_i32(&self) -> Option<i32> {
+        if self.0.is_integer() {
+            self.0.to_integer().to_i32()
+        } else {
+            None
+        }
+    }
+}
+
+starlark::starlark_simple_value!(StarlarkRational);
+
+#[starlark_value(type = "rational")]
+impl<'v> StarlarkValue<'v> for StarlarkRational {
+    fn equals(&self, other: Value<'v>) -> anyhow::Result<bool> {
+        Ok(match Num::unpack_value(other) {
+            Some(other) => Num::Rational(self) == other,
+            None => false,
+        })
+    }
+
+    fn compare(&self, other: Value<'v>) -> anyhow::Result<Ordering> {
+        match Num::unpack_value(other) {
+            Some(other) => Ok(Num::Rational(self).cmp(&other)),
+            None => Err(anyhow::anyhow!(
+                "Cannot compare rational with `{}`",
+                other.get_type()
+            )),
+        }
+    }
+
+    fn get_hash(&self) -> anyhow::Result<StarlarkHashValue> {
+        Ok(Num::Rational(self).get_hash())
+    }
+
+    /// `rational + rational`/`int`/`float`, widening to `float` for a `float`
+    /// operand as the spec's numeric promotion dictates, staying exact otherwise.
+    fn add(&self, rhs: Value<'v>, heap: &'v Heap) -> Option<anyhow::Result<Value<'v>>> {
+        match Num::unpack_value(rhs) {
+            Some(Num::Rational(r)) => Some(Ok(heap.alloc(StarlarkRational(
+                self.0.clone() + r.get().clone(),
+            )))),
+            Some(Num::Int(StarlarkIntRef::Small(i))) => Some(Ok(heap.alloc(StarlarkRational(
+                self.0.clone() + BigRational::from_integer(BigInt::from(i)),
+            )))),
+            Some(Num::Int(StarlarkIntRef::Big(b))) => Some(Ok(heap.alloc(StarlarkRational(
+                self.0.clone() + BigRational::from_integer(b.get().clone()),
+            )))),
+            Some(Num::Float(f)) => Some(Ok(heap.alloc(self.to_f64() + f))),
+            None => None,
+        }
+    }
+
+    /// `int`/`float` `+ rational`: addition is commutative, so the reflected
+    /// operand order is handled the same way as `add` above. Without this, an
+    /// `int`/`float` left operand's own `add` has no idea `rational` exists
+    /// and `1 + fraction(1, 3)` would fail instead of falling back here.
+    fn radd(&self, lhs: Value<'v>, heap: &'v Heap) -> Option<anyhow::Result<Value<'v>>> {
+        self.add(lhs, heap)
+    }
+}